@@ -2,7 +2,8 @@
 use std::collections::{HashMap, HashSet};
 
 use schemars::schema::{
-    ArrayValidation, InstanceType, ObjectValidation, RootSchema, Schema, SchemaObject, SingleOrVec,
+    ArrayValidation, InstanceType, Metadata, NumberValidation, ObjectValidation, RootSchema,
+    Schema, SchemaObject, SingleOrVec, StringValidation,
 };
 
 /// Merge multiple [schemars::schema::RootSchema] into a single [schemars::schema::RootSchema].
@@ -75,23 +76,534 @@ pub fn merge_schemas(schemas: impl Iterator<Item = RootSchema>) -> RootSchema {
 /// let converted = convert(merge_schemas(vec![schema_for!(MyStruct), schema_for!(MyOtherStruct)].into_iter()));
 /// ```
 pub fn convert(schema: RootSchema) -> HashMap<String, String> {
+    convert_with(schema, &ZodConfig::default())
+}
+
+/// Like [convert], but with a [ZodConfig] controlling how ambiguous JSON Schema
+/// constructs (references, `date-time` strings, integers) are rendered.
+///
+/// # Examples
+///
+/// ```
+/// use schemars::schema_for;
+/// use schemars_zod::{convert_with, DateTimeStrategy, IntegerStrategy, ZodConfig};
+///
+/// #[derive(schemars::JsonSchema)]
+/// struct MyStruct {/* ... */}
+///
+/// let config = ZodConfig {
+///     definitions_path: "#/components/schemas/".to_owned(),
+///     date_time: DateTimeStrategy::Date,
+///     integer: IntegerStrategy::BigInt,
+/// };
+/// let converted = convert_with(schema_for!(MyStruct), &config);
+/// ```
+pub fn convert_with(schema: RootSchema, config: &ZodConfig) -> HashMap<String, String> {
+    convert_with_emitter(schema, config, &ZodEmitter)
+}
+
+/// Like [convert_with], but rendering through a caller-supplied [Emitter] instead of the
+/// built-in [ZodEmitter]. This is how a backend other than Zod (Valibot, io-ts, plain
+/// TypeScript interfaces, ...) is plugged in: the JSON Schema walk stays the same, only
+/// the strings it produces change.
+///
+/// # Examples
+///
+/// ```
+/// use schemars::schema_for;
+/// use schemars_zod::{convert_with_emitter, ZodConfig, ZodEmitter};
+///
+/// #[derive(schemars::JsonSchema)]
+/// struct MyStruct {/* ... */}
+///
+/// let converted = convert_with_emitter(schema_for!(MyStruct), &ZodConfig::default(), &ZodEmitter);
+/// ```
+pub fn convert_with_emitter(
+    schema: RootSchema,
+    config: &ZodConfig,
+    emitter: &dyn Emitter,
+) -> HashMap<String, String> {
     let mut definitions = HashMap::new();
 
     for (id, definition) in schema.definitions {
-        add_converted_schema(&mut definitions, id, definition.into_object());
+        add_converted_schema(&mut definitions, id, definition.into_object(), config, emitter);
     }
 
     definitions
 }
 
+/// Like [convert], but returns definitions in dependency order instead of an unordered
+/// `HashMap`: a type's dependencies come before the type itself, so joining the output
+/// top-to-bottom never places a use before its definition. Types that only reach each
+/// other through a reference cycle (already rendered as `z.lazy`, so the cycle itself
+/// isn't a problem) are kept grouped together, in a stable order.
+///
+/// # Examples
+///
+/// ```
+/// use schemars::schema_for;
+/// use schemars_zod::convert_ordered;
+///
+/// #[derive(schemars::JsonSchema)]
+/// struct MyStruct {/* ... */}
+///
+/// let ordered = convert_ordered(schema_for!(MyStruct));
+/// let joined = ordered.into_iter().map(|(_, code)| code).collect::<Vec<_>>().join("\n");
+/// ```
+pub fn convert_ordered(schema: RootSchema) -> Vec<(String, String)> {
+    convert_ordered_with(schema, &ZodConfig::default())
+}
+
+/// Like [convert_ordered], but with a [ZodConfig].
+pub fn convert_ordered_with(schema: RootSchema, config: &ZodConfig) -> Vec<(String, String)> {
+    convert_ordered_with_emitter(schema, config, &ZodEmitter)
+}
+
+/// Like [convert_ordered], but rendering through a caller-supplied [Emitter].
+pub fn convert_ordered_with_emitter(
+    schema: RootSchema,
+    config: &ZodConfig,
+    emitter: &dyn Emitter,
+) -> Vec<(String, String)> {
+    let mut graph = HashMap::new();
+    for (id, definition) in &schema.definitions {
+        let mut references = HashSet::new();
+        collect_references(&definition.clone().into_object(), config, &mut references);
+        graph.insert(id.clone(), references);
+    }
+
+    let mut generated = HashMap::new();
+    for (id, definition) in schema.definitions {
+        add_converted_schema(&mut generated, id, definition.into_object(), config, emitter);
+    }
+
+    order_definitions(graph)
+        .into_iter()
+        .filter_map(|id| generated.remove(&id).map(|code| (id, code)))
+        .collect()
+}
+
+/// Collect every `$ref` target reachable from `schema` without crossing into a nested
+/// definition's own body: properties, `additionalProperties`, array items, and the
+/// arms of `oneOf`/`anyOf`/`allOf`.
+fn collect_references(schema: &SchemaObject, config: &ZodConfig, references: &mut HashSet<String>) {
+    if let Some(reference) = schema.reference.as_ref() {
+        references.insert(reference.replace(&config.definitions_path, ""));
+        return;
+    }
+
+    if let Some(object) = schema.object.as_ref() {
+        for property in object.properties.values() {
+            collect_references(&property.clone().into_object(), config, references);
+        }
+        if let Some(additional_properties) = object.additional_properties.as_ref() {
+            collect_references(&additional_properties.clone().into_object(), config, references);
+        }
+    }
+
+    if let Some(array) = schema.array.as_ref() {
+        if let Some(items) = array.items.as_ref() {
+            collect_references_from_single_or_vec(items, config, references);
+        }
+        if let Some(additional_items) = array.additional_items.as_ref() {
+            collect_references(&additional_items.clone().into_object(), config, references);
+        }
+    }
+
+    if let Some(subschemas) = schema.subschemas.as_ref() {
+        for arms in [&subschemas.one_of, &subschemas.any_of, &subschemas.all_of] {
+            let Some(arms) = arms.as_ref() else { continue; };
+            for arm in arms {
+                collect_references(&arm.clone().into_object(), config, references);
+            }
+        }
+    }
+}
+
+fn collect_references_from_single_or_vec(
+    items: &SingleOrVec<Schema>,
+    config: &ZodConfig,
+    references: &mut HashSet<String>,
+) {
+    match items {
+        SingleOrVec::Single(schema) => {
+            collect_references(&schema.clone().into_object(), config, references)
+        }
+        SingleOrVec::Vec(schemas) => {
+            for schema in schemas {
+                collect_references(&schema.clone().into_object(), config, references);
+            }
+        }
+    }
+}
+
+/// Topologically sort `graph` (`id -> the ids it references`) so dependencies precede
+/// dependents, using Tarjan's strongly-connected-components algorithm. A component
+/// completes (and is appended to the order) only once every id it reaches has also
+/// completed, so members of a reference cycle naturally end up grouped together,
+/// sorted for a stable, diffable order.
+fn order_definitions(graph: HashMap<String, HashSet<String>>) -> Vec<String> {
+    struct Tarjan<'a> {
+        graph: &'a HashMap<String, HashSet<String>>,
+        next_index: usize,
+        index: HashMap<String, usize>,
+        lowlink: HashMap<String, usize>,
+        on_stack: HashSet<String>,
+        stack: Vec<String>,
+        components: Vec<Vec<String>>,
+    }
+
+    impl<'a> Tarjan<'a> {
+        fn visit(&mut self, node: &str) {
+            self.index.insert(node.to_owned(), self.next_index);
+            self.lowlink.insert(node.to_owned(), self.next_index);
+            self.next_index += 1;
+            self.stack.push(node.to_owned());
+            self.on_stack.insert(node.to_owned());
+
+            let mut neighbors: Vec<&String> = match self.graph.get(node) {
+                Some(neighbors) => neighbors.iter().collect(),
+                None => Vec::new(),
+            };
+            neighbors.sort();
+
+            for neighbor in neighbors {
+                if !self.graph.contains_key(neighbor) {
+                    // reference escapes this definition set; nothing to order it against
+                    continue;
+                }
+
+                if !self.index.contains_key(neighbor) {
+                    self.visit(neighbor);
+                    let lowlink = self.lowlink[neighbor].min(self.lowlink[node]);
+                    self.lowlink.insert(node.to_owned(), lowlink);
+                } else if self.on_stack.contains(neighbor) {
+                    let lowlink = self.index[neighbor].min(self.lowlink[node]);
+                    self.lowlink.insert(node.to_owned(), lowlink);
+                }
+            }
+
+            if self.lowlink[node] == self.index[node] {
+                let mut component = Vec::new();
+                loop {
+                    let member = self.stack.pop().unwrap();
+                    self.on_stack.remove(&member);
+                    let done = member == node;
+                    component.push(member);
+                    if done {
+                        break;
+                    }
+                }
+                component.sort();
+                self.components.push(component);
+            }
+        }
+    }
+
+    let mut tarjan = Tarjan {
+        graph: &graph,
+        next_index: 0,
+        index: HashMap::new(),
+        lowlink: HashMap::new(),
+        on_stack: HashSet::new(),
+        stack: Vec::new(),
+        components: Vec::new(),
+    };
+
+    let mut ids: Vec<&String> = graph.keys().collect();
+    ids.sort();
+    for id in ids {
+        if !tarjan.index.contains_key(id) {
+            tarjan.visit(id);
+        }
+    }
+
+    tarjan.components.into_iter().flatten().collect()
+}
+
+/// Settings controlling how [convert_with] renders JSON Schema constructs that don't
+/// have a single canonical Zod equivalent.
+///
+/// Mirrors [schemars::gen::SchemaSettings]: a small, cloneable bag of knobs threaded
+/// through the whole conversion instead of being hardcoded or held as global state.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ZodConfig {
+    /// Prefix stripped from a `$ref` target before it's used as a `z.lazy(() => ...)`
+    /// reference name. Schemars' default generator settings produce
+    /// `#/definitions/`; its OpenAPI 3 settings produce `#/components/schemas/`.
+    pub definitions_path: String,
+    /// How `format: "date-time"` strings are represented.
+    pub date_time: DateTimeStrategy,
+    /// How `InstanceType::Integer` is represented.
+    pub integer: IntegerStrategy,
+}
+
+impl Default for ZodConfig {
+    fn default() -> Self {
+        Self {
+            definitions_path: "#/definitions/".to_owned(),
+            date_time: DateTimeStrategy::CoerceDate,
+            integer: IntegerStrategy::Int,
+        }
+    }
+}
+
+/// How `format: "date-time"` strings are rendered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DateTimeStrategy {
+    /// `z.coerce.date()` — accepts a date-time string (or a `Date`) and coerces it.
+    CoerceDate,
+    /// `z.date()` — only accepts an actual `Date` instance.
+    Date,
+}
+
+/// How `InstanceType::Integer` is rendered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IntegerStrategy {
+    /// `z.number().int()` — a JS number restricted to integer values.
+    Int,
+    /// `z.bigint()` — a JS `bigint`, for integers that may exceed `Number.MAX_SAFE_INTEGER`.
+    BigInt,
+}
+
+/// Renders the constructs the JSON Schema walk discovers into a target language/library.
+///
+/// [ZodEmitter] is the reference implementation (and the one [convert]/[convert_with] use).
+/// To retarget the generator at something other than Zod, implement this trait for a new
+/// type and drive the walk with [convert_with_emitter] instead. The walk itself (matching
+/// on `SchemaObject`, deciding which property is required, detecting a discriminated
+/// union, ...) never needs to change — only these string-emission hooks do.
+///
+/// The per-definition wrapper is not one of these hooks: whichever emitter is used, each
+/// converted definition is written as `export const {id} = ...;` followed by
+/// `export type {id} = z.infer<typeof {id}>;`.
+pub trait Emitter {
+    /// An object/struct type with the given `(name, type, required)` fields.
+    fn object(&self, fields: Vec<(String, String, bool)>) -> String;
+    /// A `Record<string, value>`-shaped map type.
+    fn record(&self, value: String) -> String;
+    /// A homogeneous array of `item`, with schemars' [ArrayValidation] constraints applied.
+    fn array(&self, item: String, validation: Option<&ArrayValidation>) -> String;
+    /// A fixed-length tuple of `items`.
+    fn tuple(&self, items: Vec<String>) -> String;
+    /// An ordinary (non-discriminated) union of `variants`.
+    fn union(&self, variants: Vec<String>) -> String;
+    /// A union discriminated by the field name shared across all of `variants`.
+    fn discriminated_union(&self, tag: String, variants: Vec<String>) -> String;
+    /// An intersection (`allOf`) of `arms`, each of which must hold at once.
+    fn intersection(&self, arms: Vec<String>) -> String;
+    /// A single literal value.
+    fn literal(&self, value: &serde_json::Value) -> String;
+    /// A fixed set of literal values.
+    fn enum_values(&self, values: &[&serde_json::Value]) -> String;
+    /// A forward reference to another named definition.
+    fn reference(&self, name: &str) -> String;
+    /// Wrap `inner` so it also accepts `null`.
+    fn nullable(&self, inner: String) -> String;
+    /// `null`.
+    fn null(&self) -> String;
+    /// `boolean`.
+    fn boolean(&self) -> String;
+    /// `number`, with schemars' [NumberValidation] constraints applied.
+    fn number(&self, validation: Option<&NumberValidation>) -> String;
+    /// An integer-only number per `config`, with [NumberValidation] constraints applied.
+    fn integer(&self, validation: Option<&NumberValidation>, config: &ZodConfig) -> String;
+    /// `string`, with schemars' [StringValidation] constraints applied.
+    fn string(&self, validation: Option<&StringValidation>) -> String;
+    /// A `format: "date-time"` string, rendered per `config`.
+    fn date_time(&self, config: &ZodConfig) -> String;
+    /// Attach a human-readable `description` to an already-rendered `value`.
+    fn describe(&self, value: String, description: &str) -> String;
+    /// Attach a default, serialized as `default_json`, to an already-rendered `value`.
+    fn default_value(&self, value: String, default_json: &str) -> String;
+}
+
+/// The built-in [Emitter] that renders [Zod](https://zod.dev) schemas. This is what
+/// [convert] and [convert_with] use.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ZodEmitter;
+
+impl Emitter for ZodEmitter {
+    fn object(&self, fields: Vec<(String, String, bool)>) -> String {
+        let mut rv = String::from("z.object({");
+        for (name, zod_type, required) in fields {
+            let optional = if required { "" } else { ".optional()" };
+            rv.push_str(&format!("{name}: {zod_type}{optional}, "));
+        }
+        rv.push_str("})");
+        rv
+    }
+
+    fn record(&self, value: String) -> String {
+        format!("z.record({value})")
+    }
+
+    fn array(&self, item: String, validation: Option<&ArrayValidation>) -> String {
+        let mut rv = format!("z.array({item})");
+
+        let Some(validation) = validation else { return rv; };
+        if let Some(min_items) = validation.min_items {
+            rv.push_str(&format!(".min({min_items})"));
+        }
+        if let Some(max_items) = validation.max_items {
+            rv.push_str(&format!(".max({max_items})"));
+        }
+        if validation.unique_items == Some(true) {
+            rv.push_str(".refine((items) => new Set(items).size === items.length)");
+        }
+
+        rv
+    }
+
+    fn tuple(&self, items: Vec<String>) -> String {
+        format!("z.tuple([{}])", items.join(", "))
+    }
+
+    fn union(&self, variants: Vec<String>) -> String {
+        format!("z.union([{}])", variants.join(", "))
+    }
+
+    fn discriminated_union(&self, tag: String, variants: Vec<String>) -> String {
+        format!("z.discriminatedUnion('{tag}', [{}])", variants.join(", "))
+    }
+
+    fn intersection(&self, arms: Vec<String>) -> String {
+        if arms.len() == 2 {
+            return format!("z.intersection({}, {})", arms[0], arms[1]);
+        }
+
+        let mut arms = arms.into_iter();
+        let mut rv = arms.next().unwrap_or_else(|| "z.unknown()".to_owned());
+        for arm in arms {
+            rv = format!("{rv}.and({arm})");
+        }
+        rv
+    }
+
+    fn literal(&self, value: &serde_json::Value) -> String {
+        format!("z.literal({})", serde_json::to_string_pretty(value).unwrap())
+    }
+
+    fn enum_values(&self, values: &[&serde_json::Value]) -> String {
+        let mut rv = String::from("z.enum([");
+        for value in values {
+            rv.push_str(&format!("{}, ", serde_json::to_string_pretty(value).unwrap()));
+        }
+        rv.push_str("])");
+        rv
+    }
+
+    fn reference(&self, name: &str) -> String {
+        format!("z.lazy(() => {name})")
+    }
+
+    fn nullable(&self, inner: String) -> String {
+        format!("{inner}.nullable()")
+    }
+
+    fn null(&self) -> String {
+        "z.null()".to_owned()
+    }
+
+    fn boolean(&self) -> String {
+        "z.boolean()".to_owned()
+    }
+
+    fn number(&self, validation: Option<&NumberValidation>) -> String {
+        append_number_validations("z.number()".to_owned(), validation)
+    }
+
+    fn integer(&self, validation: Option<&NumberValidation>, config: &ZodConfig) -> String {
+        let base = match config.integer {
+            IntegerStrategy::Int => "z.number().int()".to_owned(),
+            IntegerStrategy::BigInt => "z.bigint()".to_owned(),
+        };
+        append_number_validations(base, validation)
+    }
+
+    fn string(&self, validation: Option<&StringValidation>) -> String {
+        append_string_validations("z.string()".to_owned(), validation)
+    }
+
+    fn date_time(&self, config: &ZodConfig) -> String {
+        match config.date_time {
+            DateTimeStrategy::CoerceDate => "z.coerce.date()".to_owned(),
+            DateTimeStrategy::Date => "z.date()".to_owned(),
+        }
+    }
+
+    fn describe(&self, value: String, description: &str) -> String {
+        format!(
+            "{value}.describe({})",
+            serde_json::to_string(description).unwrap()
+        )
+    }
+
+    fn default_value(&self, value: String, default_json: &str) -> String {
+        format!("{value}.default({default_json})")
+    }
+}
+
+/// Append `.min()`/`.max()`/`.regex()` chained calls for the constraints schemars
+/// records in [StringValidation], so they survive the trip into the generated Zod schema.
+fn append_string_validations(base: String, validation: Option<&StringValidation>) -> String {
+    let Some(validation) = validation else { return base; };
+    let mut rv = base;
+
+    if let Some(min_length) = validation.min_length {
+        rv.push_str(&format!(".min({min_length})"));
+    }
+    if let Some(max_length) = validation.max_length {
+        rv.push_str(&format!(".max({max_length})"));
+    }
+    if let Some(pattern) = validation.pattern.as_ref() {
+        // Built from `new RegExp(...)` over a JSON-escaped string rather than a bare
+        // `/.../` literal, so a pattern containing an unescaped `/` (common in path or
+        // URI patterns) doesn't truncate the regex early.
+        rv.push_str(&format!(
+            ".regex(new RegExp({}))",
+            serde_json::to_string(pattern).unwrap()
+        ));
+    }
+
+    rv
+}
+
+/// Append `.gte()`/`.lte()`/`.gt()`/`.lt()`/`.multipleOf()` chained calls for the
+/// constraints schemars records in [NumberValidation].
+fn append_number_validations(base: String, validation: Option<&NumberValidation>) -> String {
+    let Some(validation) = validation else { return base; };
+    let mut rv = base;
+
+    if let Some(minimum) = validation.minimum {
+        rv.push_str(&format!(".gte({minimum})"));
+    }
+    if let Some(exclusive_minimum) = validation.exclusive_minimum {
+        rv.push_str(&format!(".gt({exclusive_minimum})"));
+    }
+    if let Some(maximum) = validation.maximum {
+        rv.push_str(&format!(".lte({maximum})"));
+    }
+    if let Some(exclusive_maximum) = validation.exclusive_maximum {
+        rv.push_str(&format!(".lt({exclusive_maximum})"));
+    }
+    if let Some(multiple_of) = validation.multiple_of {
+        rv.push_str(&format!(".multipleOf({multiple_of})"));
+    }
+
+    rv
+}
+
 fn add_converted_schema(
     definitions: &mut HashMap<String, String>,
     id: String,
     schema: SchemaObject,
+    config: &ZodConfig,
+    emitter: &dyn Emitter,
 ) {
     let mut rv = String::new();
 
-    let Some(generated) = convert_schema_object_to_zod(schema) else { return; };
+    let Some(generated) = convert_schema_object(schema, config, emitter) else { return; };
 
     rv.push_str(&format!("export const {id} = {generated};\n"));
     rv.push_str(&format!("export type {id} = z.infer<typeof {id}>;\n"));
@@ -99,55 +611,158 @@ fn add_converted_schema(
     definitions.insert(id, rv);
 }
 
-fn convert_schema_object_to_zod(schema: SchemaObject) -> Option<String> {
+fn convert_schema_object(
+    schema: SchemaObject,
+    config: &ZodConfig,
+    emitter: &dyn Emitter,
+) -> Option<String> {
+    let metadata = schema.metadata.clone();
+
+    let generated = convert_schema_object_dispatch(schema, config, emitter)?;
+
+    Some(apply_metadata(generated, metadata.as_deref(), emitter))
+}
+
+/// Append `.describe()`/`.default()` for the documentation and default value schemars
+/// records in `SchemaObject.metadata`.
+///
+/// Deliberately does *not* fall back to `SchemaObject.const_value`: a `const` is a fixed,
+/// required value (e.g. a discriminated-union tag) that `convert_single_instance_type_schema`
+/// already renders as `z.literal(...)`. Chaining `.default()` onto that — Zod's `.default()`
+/// substitutes the value whenever the input is `undefined`, independent of `.optional()` —
+/// would silently make a required literal field optional to parse.
+fn apply_metadata(generated: String, metadata: Option<&Metadata>, emitter: &dyn Emitter) -> String {
+    let mut rv = generated;
+
+    if let Some(description) = metadata.and_then(|m| m.description.as_ref()) {
+        rv = emitter.describe(rv, description);
+    }
+
+    if let Some(default_value) = metadata.and_then(|m| m.default.as_ref()) {
+        rv = emitter.default_value(rv, &serde_json::to_string(default_value).unwrap());
+    }
+
+    rv
+}
+
+fn convert_schema_object_dispatch(
+    schema: SchemaObject,
+    config: &ZodConfig,
+    emitter: &dyn Emitter,
+) -> Option<String> {
     // handle references
     if let Some(reference) = schema.reference.as_ref() {
-        let reference = reference.replace("#/definitions/", "");
-        return Some(format!("z.lazy(() => {reference})"));
+        let reference = reference.replace(&config.definitions_path, "");
+        return Some(emitter.reference(&reference));
     }
 
     // handle ordinary value disjoint unions / enums
     if let Some(enum_values) = schema.enum_values.as_ref() {
-        if enum_values.len() == 1 {
-            return Some(format!(
-                "z.literal({})",
-                serde_json::to_string_pretty(enum_values.first().unwrap()).unwrap()
-            ));
-        }
+        let has_null = enum_values.len() > 1 && enum_values.iter().any(|value| value.is_null());
+        let values: Vec<&serde_json::Value> = if has_null {
+            enum_values.iter().filter(|value| !value.is_null()).collect()
+        } else {
+            enum_values.iter().collect()
+        };
+
+        let inner = if values.len() == 1 {
+            emitter.literal(values[0])
+        } else {
+            emitter.enum_values(&values)
+        };
+
+        return Some(if has_null { emitter.nullable(inner) } else { inner });
+    }
 
-        let mut rv = String::new();
-        rv.push_str("z.enum([");
-        for value in enum_values {
-            rv.push_str(&format!(
-                "{}, ",
-                serde_json::to_string_pretty(&value).unwrap()
-            ));
+    if let Some(subschemas) = schema.subschemas.as_ref() {
+        // tagged / untagged unions, including the common `Option<T>` shape of a two-arm
+        // union where one arm is `null`
+        if let Some(one_of) = subschemas.one_of.as_ref() {
+            return convert_union_subschemas(one_of, config, emitter);
         }
-        rv.push_str("])");
+        if let Some(any_of) = subschemas.any_of.as_ref() {
+            return convert_union_subschemas(any_of, config, emitter);
+        }
+
+        // `allOf` is an intersection: every arm's constraints must hold at once, so
+        // flattened/merged structs fold into an intersection (`z.intersection(a, b)` for
+        // two arms, an `.and(...)` chain otherwise) rather than a union.
+        if let Some(all_of) = subschemas.all_of.as_ref() {
+            let mut arms = Vec::new();
+            for schema in all_of {
+                let Some(generated) = convert_schema_object(schema.clone().into_object(), config, emitter) else { continue; };
+                arms.push(generated);
+            }
+
+            // A schema can combine `allOf` with its own local `object`/`array` validation
+            // sitting alongside it (a base type pulled in via `allOf: [$ref]` plus extra
+            // fields declared directly next to it) rather than nested inside an arm. Fold
+            // that local shape in as one more intersection arm instead of silently dropping
+            // it. Checked directly against `schema.object`/`schema.array` rather than going
+            // back through `convert_schema_object_dispatch`, since that would just hit this
+            // same `subschemas.all_of` branch again.
+            if let Some(object_type) = schema.object.as_ref() {
+                if let Some(generated) = convert_object_type(object_type, &schema, config, emitter) {
+                    arms.push(generated);
+                }
+            } else if let Some(array_type) = schema.array.as_ref() {
+                if let Some(generated) = convert_array_type(array_type, &schema, config, emitter) {
+                    arms.push(generated);
+                }
+            }
 
-        return Some(rv);
+            return Some(emitter.intersection(arms));
+        }
     }
 
-    // handle tagged / untagged unions
-    if let Some(one_of) = schema.subschemas.as_ref().and_then(|x| x.one_of.as_ref()) {
-        let mut rv = if let Some(field) = all_schemas_share_a_field(one_of) {
-            format!("z.discriminatedUnion('{field}', [")
-        } else {
-            format!("z.union([")
-        };
+    let Some(instance_type) = schema.instance_type.as_ref() else { return None; };
 
-        for schema in one_of {
-            let Some(generated) = convert_schema_object_to_zod(schema.clone().into_object()) else { continue; };
-            rv.push_str(&format!("{generated}, "));
+    convert_schema_type(instance_type, &schema, config, emitter)
+}
+
+/// Shared logic for `oneOf`/`anyOf`: recognize the two-arm `Option<T>` shape (one arm
+/// is `null`) and otherwise emit a discriminated union when every arm shares a field,
+/// falling back to a plain union.
+fn convert_union_subschemas(
+    schemas: &[Schema],
+    config: &ZodConfig,
+    emitter: &dyn Emitter,
+) -> Option<String> {
+    if schemas.len() == 2 {
+        let arms: Vec<SchemaObject> = schemas.iter().map(|s| s.clone().into_object()).collect();
+        if let Some(null_index) = arms.iter().position(schema_is_null) {
+            let inner_schema = arms[1 - null_index].clone();
+            let Some(inner) = convert_schema_object(inner_schema, config, emitter) else { return None; };
+            return Some(emitter.nullable(inner));
         }
+    }
 
-        rv.push_str("])");
-        return Some(rv);
+    let field = all_schemas_share_a_field(schemas);
+    let mut variants = Vec::new();
+    for schema in schemas {
+        let Some(generated) = convert_schema_object(schema.clone().into_object(), config, emitter) else { continue; };
+        variants.push(generated);
     }
 
-    let Some(instance_type) = schema.instance_type.as_ref() else { return None; };
+    Some(match field {
+        Some(tag) => emitter.discriminated_union(tag, variants),
+        None => emitter.union(variants),
+    })
+}
 
-    convert_schema_type_to_zod(instance_type, &schema)
+/// Does this schema only ever describe the JSON value `null`?
+///
+/// Used to recognize the `Option<T>` shape schemars emits as a two-arm `one_of`/`anyOf`
+/// (one arm being `null`). The two-entry `instance_type` form (e.g. `["string", "null"]`)
+/// is recognized separately, in [convert_union_type_schema].
+fn schema_is_null(schema: &SchemaObject) -> bool {
+    matches!(
+        schema.instance_type.as_ref(),
+        Some(SingleOrVec::Single(instance_type)) if **instance_type == InstanceType::Null
+    ) || matches!(
+        schema.enum_values.as_ref(),
+        Some(values) if values.len() == 1 && values[0].is_null()
+    )
 }
 
 fn all_schemas_share_a_field(any_of: &[Schema]) -> Option<String> {
@@ -185,127 +800,366 @@ fn all_schemas_share_a_field(any_of: &[Schema]) -> Option<String> {
     })
 }
 
-fn convert_schema_type_to_zod(
+fn convert_schema_type(
     instance_type: &SingleOrVec<InstanceType>,
     schema: &SchemaObject,
+    config: &ZodConfig,
+    emitter: &dyn Emitter,
 ) -> Option<String> {
     match instance_type {
         SingleOrVec::Single(single_type) => {
-            convert_single_instance_type_schema_to_zod(single_type, &schema)
+            convert_single_instance_type_schema(single_type, &schema, config, emitter)
         }
         SingleOrVec::Vec(multiple_types) => {
-            convert_union_type_schema_to_zod(multiple_types, &schema)
+            convert_union_type_schema(multiple_types, &schema, config, emitter)
         }
     }
 }
 
-fn convert_single_instance_type_schema_to_zod(
+fn convert_single_instance_type_schema(
     instance_type: &Box<InstanceType>,
     schema: &SchemaObject,
+    config: &ZodConfig,
+    emitter: &dyn Emitter,
 ) -> Option<String> {
     if let Some(literal_value) = schema.const_value.as_ref() {
-        return Some(format!(
-            "z.literal({})",
-            serde_json::to_string_pretty(literal_value).unwrap()
-        ));
+        return Some(emitter.literal(literal_value));
     }
 
     match instance_type.as_ref() {
-        InstanceType::Null => Some(format!("z.null()")),
-        InstanceType::Boolean => Some(format!("z.boolean()")),
-        InstanceType::Object => convert_object_type_to_zod(schema.object.as_ref().unwrap(), schema),
-        InstanceType::Array => convert_array_type_to_zod(schema.array.as_ref().unwrap(), schema),
-        InstanceType::Number => Some(format!("z.number()")),
+        InstanceType::Null => Some(emitter.null()),
+        InstanceType::Boolean => Some(emitter.boolean()),
+        InstanceType::Object => {
+            convert_object_type(schema.object.as_ref().unwrap(), schema, config, emitter)
+        }
+        InstanceType::Array => {
+            convert_array_type(schema.array.as_ref().unwrap(), schema, config, emitter)
+        }
+        InstanceType::Number => Some(emitter.number(schema.number.as_deref())),
         InstanceType::String => {
             if matches!(schema.format.as_ref(), Some(format) if format == "date-time") {
-                return Some(format!("z.coerce.date()"));
+                return Some(emitter.date_time(config));
             }
-            Some(format!("z.string()"))
+            Some(emitter.string(schema.string.as_deref()))
         }
-        InstanceType::Integer => Some(format!("z.number().int()")),
+        InstanceType::Integer => Some(emitter.integer(schema.number.as_deref(), config)),
     }
 }
 
-fn convert_array_type_to_zod(
+fn convert_array_type(
     array_type: &Box<ArrayValidation>,
     schema: &SchemaObject,
+    config: &ZodConfig,
+    emitter: &dyn Emitter,
 ) -> Option<String> {
     let Some(items) = array_type.items.as_ref() else { return None; };
 
     if array_type.min_items.is_some() && array_type.min_items == array_type.max_items {
-        convert_schema_or_ref_to_zod(items, "tuple")
+        convert_schema_or_ref(items, TupleOrUnion::Tuple, config, emitter)
     } else {
-        let mut rv = String::new();
-        rv.push_str("z.array(");
-        let Some(generated) = convert_schema_or_ref_to_zod(items, "union") else { return None; };
-        rv.push_str(&format!("{generated})"));
-        Some(rv)
+        let Some(item) = convert_schema_or_ref(items, TupleOrUnion::Union, config, emitter) else { return None; };
+        Some(emitter.array(item, Some(array_type)))
     }
 }
 
-fn convert_schema_or_ref_to_zod(schema: &SingleOrVec<Schema>, zod_mode: &str) -> Option<String> {
+/// Whether a multi-schema `items` list is rendered as a fixed-length tuple (when
+/// `minItems == maxItems`) or as a plain union used as an array's element type.
+enum TupleOrUnion {
+    Tuple,
+    Union,
+}
+
+fn convert_schema_or_ref(
+    schema: &SingleOrVec<Schema>,
+    mode: TupleOrUnion,
+    config: &ZodConfig,
+    emitter: &dyn Emitter,
+) -> Option<String> {
     match schema {
         SingleOrVec::Single(schema_or_ref) => {
-            convert_schema_object_to_zod(schema_or_ref.clone().into_object())
+            convert_schema_object(schema_or_ref.clone().into_object(), config, emitter)
         }
         SingleOrVec::Vec(schemas) => {
             if schemas.len() == 1 {
-                return convert_schema_object_to_zod(
+                return convert_schema_object(
                     schemas.first().unwrap().clone().into_object(),
+                    config,
+                    emitter,
                 );
             }
 
-            let mut rv = String::new();
-            rv.push_str(&format!("z.{zod_mode}(["));
+            let mut variants = Vec::new();
             for schema in schemas {
-                if let Some(schema) = convert_schema_object_to_zod(schema.clone().into_object()) {
-                    rv.push_str(&format!("{schema}, ",));
+                if let Some(schema) = convert_schema_object(schema.clone().into_object(), config, emitter) {
+                    variants.push(schema);
                 }
             }
-            rv.push_str("])");
-            Some(rv)
+
+            Some(match mode {
+                TupleOrUnion::Tuple => emitter.tuple(variants),
+                TupleOrUnion::Union => emitter.union(variants),
+            })
         }
     }
 }
 
-fn convert_object_type_to_zod(
+fn convert_object_type(
     object_type: &Box<ObjectValidation>,
     schema: &SchemaObject,
+    config: &ZodConfig,
+    emitter: &dyn Emitter,
 ) -> Option<String> {
-    let mut rv = String::new();
-
     // are we additional objects and no properties? if so, we are a record
     if object_type.additional_properties.is_some() && object_type.properties.is_empty() {
         let Some(additional_properties) = object_type.additional_properties.as_ref() else { return None; };
-        let Some(additional_properties) = convert_schema_object_to_zod(additional_properties.clone().into_object()) else { return None; };
-        return Some(format!("z.record({additional_properties})"));
+        let Some(additional_properties) =
+            convert_schema_object(additional_properties.clone().into_object(), config, emitter)
+        else {
+            return None;
+        };
+        return Some(emitter.record(additional_properties));
     }
 
-    rv.push_str("z.object({");
-
+    let mut fields = Vec::new();
     for (property_name, property) in &object_type.properties {
-        let Some(property_type) = convert_schema_object_to_zod(property.clone().into_object()) else { return None; };
-        rv.push_str(&format!("{property_name}: {property_type}, ",));
+        let Some(property_type) = convert_schema_object(property.clone().into_object(), config, emitter) else { return None; };
+        let required = object_type.required.contains(property_name);
+        fields.push((property_name.clone(), property_type, required));
     }
 
-    rv.push_str("})");
-
-    Some(rv)
+    Some(emitter.object(fields))
 }
 
-fn convert_union_type_schema_to_zod(
+fn convert_union_type_schema(
     instance_types: &Vec<InstanceType>,
     schema: &SchemaObject,
+    config: &ZodConfig,
+    emitter: &dyn Emitter,
 ) -> Option<String> {
-    let mut rv = String::new();
+    if instance_types.len() == 2 {
+        if let Some(null_index) = instance_types.iter().position(|t| *t == InstanceType::Null) {
+            let other = &instance_types[1 - null_index];
+            let Some(inner) = convert_single_instance_type_schema(
+                &Box::new(other.clone()),
+                schema,
+                config,
+                emitter,
+            ) else {
+                return None;
+            };
+            return Some(emitter.nullable(inner));
+        }
+    }
 
-    rv.push_str("z.union([");
+    let mut variants = Vec::new();
     for instance_type in instance_types {
-        let Some(generated) = convert_single_instance_type_schema_to_zod(&Box::new(instance_type.clone()), schema) else { return None; };
-        rv.push_str(&format!("{generated}, "));
+        let Some(generated) = convert_single_instance_type_schema(&Box::new(instance_type.clone()), schema, config, emitter) else { return None; };
+        variants.push(generated);
+    }
+
+    Some(emitter.union(variants))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn refs(names: &[&str]) -> HashSet<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn order_definitions_puts_a_diamond_dependencys_leaves_first() {
+        let mut graph = HashMap::new();
+        graph.insert("A".to_owned(), refs(&["B", "C"]));
+        graph.insert("B".to_owned(), refs(&["D"]));
+        graph.insert("C".to_owned(), refs(&["D"]));
+        graph.insert("D".to_owned(), refs(&[]));
+
+        let order = order_definitions(graph);
+        let position = |id: &str| order.iter().position(|x| x == id).unwrap();
+
+        assert!(position("D") < position("B"));
+        assert!(position("D") < position("C"));
+        assert!(position("B") < position("A"));
+        assert!(position("C") < position("A"));
     }
 
-    rv.push_str("])");
+    #[test]
+    fn order_definitions_keeps_a_reference_cycle_grouped_together() {
+        let mut graph = HashMap::new();
+        graph.insert("X".to_owned(), refs(&["Y"]));
+        graph.insert("Y".to_owned(), refs(&["X"]));
+        graph.insert("Z".to_owned(), refs(&["X"]));
 
-    Some(rv)
+        let order = order_definitions(graph);
+        let position = |id: &str| order.iter().position(|x| x == id).unwrap();
+
+        assert_eq!(
+            (position("X") as isize - position("Y") as isize).abs(),
+            1,
+            "cycle members X and Y should be adjacent in the output: {order:?}"
+        );
+        assert!(position("X") < position("Z"));
+        assert!(position("Y") < position("Z"));
+    }
+
+    #[test]
+    fn all_of_with_sibling_object_properties_folds_into_the_intersection() {
+        let base_ref = Schema::Object(SchemaObject {
+            reference: Some("#/definitions/Base".to_owned()),
+            ..Default::default()
+        });
+
+        let mut object_type = ObjectValidation::default();
+        object_type.properties.insert(
+            "extra".to_owned(),
+            Schema::Object(SchemaObject {
+                instance_type: Some(SingleOrVec::Single(Box::new(InstanceType::String))),
+                ..Default::default()
+            }),
+        );
+        object_type.required.insert("extra".to_owned());
+
+        let schema = SchemaObject {
+            instance_type: Some(SingleOrVec::Single(Box::new(InstanceType::Object))),
+            subschemas: Some(Box::new(schemars::schema::SubschemaValidation {
+                all_of: Some(vec![base_ref]),
+                ..Default::default()
+            })),
+            object: Some(Box::new(object_type)),
+            ..Default::default()
+        };
+
+        let generated = convert_schema_object_dispatch(schema, &ZodConfig::default(), &ZodEmitter).unwrap();
+
+        assert!(generated.contains("z.lazy(() => Base)"), "missing allOf arm: {generated}");
+        assert!(
+            generated.contains("extra: z.string()"),
+            "sibling object properties were dropped: {generated}"
+        );
+    }
+
+    #[test]
+    fn apply_metadata_emits_default_from_metadata_default() {
+        let schema = SchemaObject {
+            instance_type: Some(SingleOrVec::Single(Box::new(InstanceType::String))),
+            metadata: Some(Box::new(Metadata {
+                default: Some(serde_json::json!("hello")),
+                ..Default::default()
+            })),
+            ..Default::default()
+        };
+
+        let generated = convert_schema_object(schema, &ZodConfig::default(), &ZodEmitter).unwrap();
+
+        assert_eq!(generated, "z.string().default(\"hello\")");
+    }
+
+    #[test]
+    fn apply_metadata_does_not_default_a_const_literal() {
+        // Regression test: a `const` (e.g. a discriminated-union tag) is a fixed,
+        // required value, not a `#[serde(default)]` field. It must render as a plain
+        // `z.literal(...)` with no `.default()` chained on, even though it's the same
+        // kind of "fixed value" a default would otherwise supply.
+        let schema = SchemaObject {
+            instance_type: Some(SingleOrVec::Single(Box::new(InstanceType::String))),
+            const_value: Some(serde_json::json!("tag")),
+            ..Default::default()
+        };
+
+        let generated = convert_schema_object(schema, &ZodConfig::default(), &ZodEmitter).unwrap();
+
+        assert_eq!(generated, "z.literal(\"tag\")");
+        assert!(!generated.contains(".default("), "const_value must not leak into .default(): {generated}");
+    }
+
+    #[test]
+    fn string_pattern_containing_a_slash_uses_new_regexp_not_a_bare_literal() {
+        // Regression test: a bare `/{pattern}/` literal breaks on patterns like this
+        // one, which is a realistic path/URI pattern containing unescaped `/`.
+        let validation = StringValidation {
+            pattern: Some("^/api/v[0-9]+/.*$".to_owned()),
+            ..Default::default()
+        };
+
+        let generated = append_string_validations("z.string()".to_owned(), Some(&validation));
+
+        assert_eq!(
+            generated,
+            "z.string().regex(new RegExp(\"^/api/v[0-9]+/.*$\"))"
+        );
+    }
+
+    #[test]
+    fn convert_object_type_marks_required_and_optional_fields() {
+        let mut object_type = ObjectValidation::default();
+        object_type.properties.insert(
+            "a".to_owned(),
+            Schema::Object(SchemaObject {
+                instance_type: Some(SingleOrVec::Single(Box::new(InstanceType::String))),
+                ..Default::default()
+            }),
+        );
+        object_type.properties.insert(
+            "b".to_owned(),
+            Schema::Object(SchemaObject {
+                instance_type: Some(SingleOrVec::Single(Box::new(InstanceType::Number))),
+                ..Default::default()
+            }),
+        );
+        object_type.required.insert("a".to_owned());
+
+        let schema = SchemaObject::default();
+        let generated =
+            convert_object_type(&Box::new(object_type), &schema, &ZodConfig::default(), &ZodEmitter).unwrap();
+
+        assert!(generated.contains("a: z.string(), "), "required field must not be optional: {generated}");
+        assert!(generated.contains("b: z.number().optional(), "), "non-required field must be optional: {generated}");
+    }
+
+    #[test]
+    fn instance_type_null_union_renders_as_nullable() {
+        // Schemars' usual shape for `Option<T>`: a single schema with a two-element
+        // `instance_type` (e.g. `["string", "null"]`), rather than a `oneOf`/`anyOf`.
+        let schema = SchemaObject::default();
+        let instance_types = vec![InstanceType::String, InstanceType::Null];
+
+        let generated =
+            convert_union_type_schema(&instance_types, &schema, &ZodConfig::default(), &ZodEmitter).unwrap();
+
+        assert_eq!(generated, "z.string().nullable()");
+    }
+
+    #[test]
+    fn one_of_null_arm_renders_as_nullable() {
+        // The other shape schemars can emit for `Option<T>`: a two-arm `oneOf`/`anyOf`
+        // where one arm is `null`, rather than a two-element `instance_type`.
+        let inner = Schema::Object(SchemaObject {
+            instance_type: Some(SingleOrVec::Single(Box::new(InstanceType::String))),
+            ..Default::default()
+        });
+        let null_arm = Schema::Object(SchemaObject {
+            instance_type: Some(SingleOrVec::Single(Box::new(InstanceType::Null))),
+            ..Default::default()
+        });
+
+        let generated =
+            convert_union_subschemas(&[inner, null_arm], &ZodConfig::default(), &ZodEmitter).unwrap();
+
+        assert_eq!(generated, "z.string().nullable()");
+    }
+
+    #[test]
+    fn enum_values_with_null_render_as_nullable() {
+        // A third `Option<T>`-ish shape: a single-member enum plus `null`.
+        let schema = SchemaObject {
+            enum_values: Some(vec![serde_json::json!("a"), serde_json::Value::Null]),
+            ..Default::default()
+        };
+
+        let generated = convert_schema_object_dispatch(schema, &ZodConfig::default(), &ZodEmitter).unwrap();
+
+        assert_eq!(generated, "z.literal(\"a\").nullable()");
+    }
 }